@@ -8,15 +8,26 @@ use deno_core::ErrBox;
 use deno_core::ZeroCopyBuf;
 use futures::future::poll_fn;
 use futures::future::FutureExt;
+use notify::event::CreateKind;
 use notify::event::Event as NotifyEvent;
+use notify::event::ModifyKind;
+use notify::event::RemoveKind;
+use notify::event::RenameMode;
 use notify::Error as NotifyError;
 use notify::EventKind;
 use notify::RecommendedWatcher;
 use notify::RecursiveMode;
 use notify::Watcher;
 use serde::Serialize;
+use std::collections::HashMap;
 use std::convert::From;
+use std::path::Path;
 use std::path::PathBuf;
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
 use tokio::sync::mpsc;
 
 pub fn init(i: &mut CoreIsolate, s: &State) {
@@ -24,10 +35,18 @@ pub fn init(i: &mut CoreIsolate, s: &State) {
   i.register_op("op_fs_events_poll", s.stateful_json_op2(op_fs_events_poll));
 }
 
+/// Default bound for the event channel, used when the caller does not request
+/// a different capacity.
+const DEFAULT_CHANNEL_CAPACITY: usize = 16;
+
 struct FsEventsResource {
   #[allow(unused)]
   watcher: RecommendedWatcher,
   receiver: mpsc::Receiver<Result<FsEvent, ErrBox>>,
+  /// Number of events dropped because the channel was full. Drained by the poll
+  /// side, which then emits a synthetic "overflow" event so consumers know
+  /// their view is stale and can force a rescan.
+  dropped: Arc<AtomicU32>,
 }
 
 /// Represents a file system event.
@@ -38,10 +57,65 @@ struct FsEventsResource {
 ///
 /// Feel free to expand this struct as long as you can add tests to demonstrate
 /// the complexity.
-#[derive(Serialize, Debug)]
+#[derive(Serialize, Debug, Clone)]
 struct FsEvent {
   kind: String,
+  /// Affected paths. For a `detail:"rename:both"` move this holds the source
+  /// path followed by the destination, so JS callers can reconstruct the move.
+  /// The pair is kept together end-to-end, including through the debounce layer.
   paths: Vec<PathBuf>,
+  /// Fine-grained sub-kind reported by the backend, e.g. "file"/"folder" for
+  /// create and remove, or "rename"/"data"/"metadata" for modify. `None` when
+  /// the backend only reported the coarse kind.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  detail: Option<String>,
+  /// Number of dropped events, set only on the synthetic "overflow" event.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  count: Option<u32>,
+  /// Backend tracker ID correlating the two halves of a rename, when provided.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  tracker: Option<usize>,
+  /// Backend flag/info hints (e.g. "Rescan"), when provided.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  flag: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  info: Option<String>,
+}
+
+impl FsEvent {
+  /// Builds the synthetic event used to tell consumers that `count` events were
+  /// dropped because the channel overflowed.
+  fn overflow(count: u32) -> Self {
+    FsEvent {
+      kind: "overflow".to_string(),
+      paths: Vec::new(),
+      detail: None,
+      count: Some(count),
+      tracker: None,
+      flag: None,
+      info: None,
+    }
+  }
+}
+
+/// Maps `notify`'s sub-kind enums to the short strings we expose to JS. For a
+/// rename we also note which half (`from`/`to`/`both`) so callers can pair the
+/// two paths of a move back together.
+fn event_detail(kind: EventKind) -> Option<String> {
+  let detail = match kind {
+    EventKind::Create(CreateKind::File) => "file",
+    EventKind::Create(CreateKind::Folder) => "folder",
+    EventKind::Remove(RemoveKind::File) => "file",
+    EventKind::Remove(RemoveKind::Folder) => "folder",
+    EventKind::Modify(ModifyKind::Data(_)) => "data",
+    EventKind::Modify(ModifyKind::Metadata(_)) => "metadata",
+    EventKind::Modify(ModifyKind::Name(RenameMode::From)) => "rename:from",
+    EventKind::Modify(ModifyKind::Name(RenameMode::To)) => "rename:to",
+    EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => "rename:both",
+    EventKind::Modify(ModifyKind::Name(_)) => "rename",
+    _ => return None,
+  };
+  Some(detail.to_string())
 }
 
 impl From<NotifyEvent> for FsEvent {
@@ -52,12 +126,150 @@ impl From<NotifyEvent> for FsEvent {
       EventKind::Create(_) => "create",
       EventKind::Modify(_) => "modify",
       EventKind::Remove(_) => "remove",
-      EventKind::Other => todo!(), // What's this for? Leaving it out for now.
+      EventKind::Other => "other",
     }
     .to_string();
+    let detail = event_detail(e.kind);
+    let tracker = e.attrs.tracker();
+    let flag = e.attrs.flag().map(|f| format!("{:?}", f));
+    let info = e.attrs.info().map(|i| i.to_string());
     FsEvent {
       kind,
       paths: e.paths,
+      detail,
+      count: None,
+      tracker,
+      flag,
+      info,
+    }
+  }
+}
+
+/// Coalescing priority of the coarse kinds. A higher value wins when two
+/// events for the same path are merged within the debounce window, so that
+/// create+modify collapses to create and anything+remove collapses to remove.
+fn kind_rank(kind: &str) -> u8 {
+  match kind {
+    "remove" => 4,
+    "create" => 3,
+    "modify" => 2,
+    _ => 1,
+  }
+}
+
+impl FsEvent {
+  /// Merges `other` (a later event for the same path) into `self`, keeping the
+  /// higher-ranked kind along with its detail and paths.
+  fn merge(&mut self, other: FsEvent) {
+    if kind_rank(&other.kind) >= kind_rank(&self.kind) {
+      self.kind = other.kind;
+      self.detail = other.detail;
+      self.paths = other.paths;
+    }
+  }
+}
+
+/// Canonicalizes a path for use as a pending-event key so the same file seen
+/// under different spellings coalesces. Falls back to the raw path when
+/// canonicalization fails (e.g. the file was just removed).
+fn canonical_key(path: &Path) -> PathBuf {
+  std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Buffers per-path events for `window` before forwarding them, collapsing the
+/// burst of events an editor produces on a single save into one event per path.
+/// The task exits once the watcher side of `raw_receiver` is dropped.
+async fn debounce_task(
+  mut raw_receiver: mpsc::Receiver<Result<FsEvent, ErrBox>>,
+  mut sender: mpsc::Sender<Result<FsEvent, ErrBox>>,
+  window: Duration,
+) {
+  let mut pending: HashMap<PathBuf, (FsEvent, Instant)> = HashMap::new();
+  loop {
+    let delay = pending
+      .values()
+      .map(|(_, t)| *t + window)
+      .min()
+      .map(|deadline| deadline.saturating_duration_since(Instant::now()));
+    tokio::select! {
+      maybe_result = raw_receiver.recv() => match maybe_result {
+        Some(Ok(event)) => {
+          let now = Instant::now();
+          // A rename:both event carries [from, to] and must stay together so
+          // the move can be reconstructed; it is keyed by its source path.
+          // Every other event is split per path so each file coalesces on its
+          // own. Canonicalize the key so different spellings of the same file
+          // land in the same entry.
+          let groups = if event.detail.as_deref() == Some("rename:both") {
+            vec![event]
+          } else {
+            event
+              .paths
+              .iter()
+              .map(|path| {
+                let mut single = event.clone();
+                single.paths = vec![path.clone()];
+                single
+              })
+              .collect::<Vec<_>>()
+          };
+          for group in groups {
+            let key = match group.paths.first() {
+              Some(path) => canonical_key(path),
+              None => continue,
+            };
+            pending
+              .entry(key)
+              .and_modify(|(e, t)| {
+                e.merge(group.clone());
+                *t = now;
+              })
+              .or_insert((group, now));
+          }
+        }
+        Some(Err(err)) => {
+          let _ = sender.send(Err(err)).await;
+        }
+        None => {
+          flush_pending(&mut pending, &mut sender, None).await;
+          break;
+        }
+      },
+      _ = maybe_delay(delay) => {
+        flush_pending(&mut pending, &mut sender, Some(window)).await;
+      }
+    }
+  }
+}
+
+/// Never resolves when `delay` is `None` (no timer armed), otherwise resolves
+/// after the given duration.
+async fn maybe_delay(delay: Option<Duration>) {
+  match delay {
+    Some(d) => tokio::time::delay_for(d).await,
+    None => futures::future::pending::<()>().await,
+  }
+}
+
+/// Forwards every entry whose last update is at least `window` old (or all
+/// entries when `window` is `None`, used on shutdown).
+async fn flush_pending(
+  pending: &mut HashMap<PathBuf, (FsEvent, Instant)>,
+  sender: &mut mpsc::Sender<Result<FsEvent, ErrBox>>,
+  window: Option<Duration>,
+) {
+  let now = Instant::now();
+  let ready: Vec<PathBuf> = pending
+    .iter()
+    .filter(|(_, (_, t))| match window {
+      Some(w) => now.duration_since(**t) >= w,
+      None => true,
+    })
+    .map(|(p, _)| p.clone())
+    .collect();
+  for path in ready {
+    if let Some((event, _)) = pending.remove(&path) {
+      let _ = sender.send(Ok(event)).await;
     }
   }
 }
@@ -66,16 +278,37 @@ fn create_resource(
   paths: &[PathBuf],
   recursive_mode: RecursiveMode,
   state: Option<&State>,
+  debounce_ms: u64,
+  capacity: usize,
 ) -> Result<FsEventsResource, deno_core::ErrBox> {
-  let (sender, receiver) = mpsc::channel::<Result<FsEvent, ErrBox>>(16);
-  let sender = std::sync::Mutex::new(sender);
+  let (sender, receiver) = mpsc::channel::<Result<FsEvent, ErrBox>>(capacity);
+  // When debouncing is enabled the watcher feeds a raw channel that a debounce
+  // task drains into `sender`; otherwise it writes to `sender` directly.
+  let watcher_sender = if debounce_ms > 0 {
+    let (raw_sender, raw_receiver) =
+      mpsc::channel::<Result<FsEvent, ErrBox>>(capacity);
+    tokio::spawn(debounce_task(
+      raw_receiver,
+      sender,
+      Duration::from_millis(debounce_ms),
+    ));
+    raw_sender
+  } else {
+    sender
+  };
+  let watcher_sender = std::sync::Mutex::new(watcher_sender);
+  let dropped = Arc::new(AtomicU32::new(0));
+  let dropped_inner = dropped.clone();
   let mut watcher: RecommendedWatcher =
     Watcher::new_immediate(move |res: Result<NotifyEvent, NotifyError>| {
       let res2 = res.map(FsEvent::from).map_err(ErrBox::from);
-      let mut sender = sender.lock().unwrap();
-      // Ignore result, if send failed it means that watcher was already closed,
-      // but not all messages have been flushed.
-      let _ = sender.try_send(res2);
+      let mut sender = watcher_sender.lock().unwrap();
+      // If the send fails because the buffer is full, record the drop so the
+      // poll side can surface it as an "overflow" event. A failure on a closed
+      // channel is harmless: the watcher is being torn down.
+      if sender.try_send(res2).is_err() {
+        dropped_inner.fetch_add(1, Ordering::Relaxed);
+      }
     })
     .map_err(ErrBox::from)?;
 
@@ -85,7 +318,11 @@ fn create_resource(
     }
     watcher.watch(path, recursive_mode).map_err(ErrBox::from)?;
   }
-  Ok(FsEventsResource { watcher, receiver })
+  Ok(FsEventsResource {
+    watcher,
+    receiver,
+    dropped,
+  })
 }
 
 pub fn op_fs_events_open(
@@ -98,16 +335,29 @@ pub fn op_fs_events_open(
   struct OpenArgs {
     recursive: bool,
     paths: Vec<String>,
+    #[serde(default)]
+    debounce_ms: u64,
+    #[serde(default)]
+    channel_capacity: Option<usize>,
   }
   let args: OpenArgs = serde_json::from_value(args)?;
+  let capacity = args
+    .channel_capacity
+    .filter(|c| *c > 0)
+    .unwrap_or(DEFAULT_CHANNEL_CAPACITY);
   let recursive_mode = if args.recursive {
     RecursiveMode::Recursive
   } else {
     RecursiveMode::NonRecursive
   };
   let path_vec = args.paths.iter().map(PathBuf::from).collect::<Vec<_>>();
-  let resource =
-    create_resource(&path_vec.as_slice(), recursive_mode, Some(&state))?;
+  let resource = create_resource(
+    &path_vec.as_slice(),
+    recursive_mode,
+    Some(&state),
+    args.debounce_ms,
+    capacity,
+  )?;
   let mut resource_table = isolate_state.resource_table.borrow_mut();
   let rid = resource_table.add("fsEvents", Box::new(resource));
   Ok(JsonOp::Sync(json!(rid)))
@@ -130,6 +380,13 @@ pub fn op_fs_events_poll(
     let resource = resource_table
       .get_mut::<FsEventsResource>(rid)
       .ok_or_else(OpError::bad_resource_id)?;
+    // Surface any dropped events first, so consumers learn their view is stale
+    // before they see the events that came after the overflow.
+    let dropped = resource.dropped.swap(0, Ordering::Relaxed);
+    if dropped > 0 {
+      let value = FsEvent::overflow(dropped);
+      return std::task::Poll::Ready(Ok(json!({ "value": value, "done": false })));
+    }
     resource
       .receiver
       .poll_recv(cx)
@@ -142,12 +399,21 @@ pub fn op_fs_events_poll(
   Ok(JsonOp::Async(f.boxed_local()))
 }
 
+/// Debounce window for the live-reload watcher, so a single save that produces
+/// a burst of backend events triggers exactly one reload.
+const FILE_WATCHER_DEBOUNCE_MS: u64 = 100;
+
 pub async fn file_watcher(
   paths: &[PathBuf],
 ) -> Result<serde_json::Value, deno_core::ErrBox> {
   loop {
-    let mut resource =
-      create_resource(paths, RecursiveMode::Recursive, None::<&State>)?;
+    let mut resource = create_resource(
+      paths,
+      RecursiveMode::Recursive,
+      None::<&State>,
+      FILE_WATCHER_DEBOUNCE_MS,
+      DEFAULT_CHANNEL_CAPACITY,
+    )?;
     let f = poll_fn(move |cx| {
       resource
         .receiver
@@ -170,3 +436,128 @@ pub async fn file_watcher(
     }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use notify::event::DataChange;
+
+  #[test]
+  fn event_detail_maps_sub_kinds() {
+    assert_eq!(
+      event_detail(EventKind::Create(CreateKind::File)).as_deref(),
+      Some("file")
+    );
+    assert_eq!(
+      event_detail(EventKind::Create(CreateKind::Folder)).as_deref(),
+      Some("folder")
+    );
+    assert_eq!(
+      event_detail(EventKind::Remove(RemoveKind::Folder)).as_deref(),
+      Some("folder")
+    );
+    assert_eq!(
+      event_detail(EventKind::Modify(ModifyKind::Data(DataChange::Content)))
+        .as_deref(),
+      Some("data")
+    );
+    assert_eq!(
+      event_detail(EventKind::Modify(ModifyKind::Name(RenameMode::Both)))
+        .as_deref(),
+      Some("rename:both")
+    );
+    assert_eq!(event_detail(EventKind::Other), None);
+  }
+
+  #[test]
+  fn from_rename_both_keeps_source_and_dest_paired() {
+    let from = PathBuf::from("/tmp/from.txt");
+    let to = PathBuf::from("/tmp/to.txt");
+    let event = NotifyEvent {
+      kind: EventKind::Modify(ModifyKind::Name(RenameMode::Both)),
+      paths: vec![from.clone(), to.clone()],
+      attrs: Default::default(),
+    };
+    let fs_event = FsEvent::from(event);
+    assert_eq!(fs_event.kind, "modify");
+    assert_eq!(fs_event.detail.as_deref(), Some("rename:both"));
+    assert_eq!(fs_event.paths, vec![from, to]);
+  }
+
+  fn event(kind: &str, path: &str) -> FsEvent {
+    FsEvent {
+      kind: kind.to_string(),
+      paths: vec![PathBuf::from(path)],
+      detail: None,
+      count: None,
+      tracker: None,
+      flag: None,
+      info: None,
+    }
+  }
+
+  #[test]
+  fn overflow_event_serializes_with_count() {
+    let value = serde_json::to_value(&FsEvent::overflow(7)).unwrap();
+    assert_eq!(value["kind"], "overflow");
+    assert_eq!(value["count"], 7);
+    // Optional fields stay absent rather than serializing as null.
+    assert!(value.get("detail").is_none());
+    assert!(value.get("tracker").is_none());
+    assert_eq!(value["paths"].as_array().unwrap().len(), 0);
+  }
+
+  #[test]
+  fn merge_collapses_by_rank() {
+    // create + modify collapses to create.
+    let mut e = event("create", "/tmp/a");
+    e.merge(event("modify", "/tmp/a"));
+    assert_eq!(e.kind, "create");
+
+    // anything + remove collapses to remove.
+    let mut e = event("modify", "/tmp/a");
+    e.merge(event("remove", "/tmp/a"));
+    assert_eq!(e.kind, "remove");
+
+    let mut e = event("create", "/tmp/a");
+    e.merge(event("remove", "/tmp/a"));
+    assert_eq!(e.kind, "remove");
+  }
+
+  #[tokio::test]
+  async fn debounce_coalesces_burst_per_path() {
+    let (mut raw_tx, raw_rx) = mpsc::channel(16);
+    let (out_tx, mut out_rx) = mpsc::channel(16);
+    tokio::spawn(debounce_task(raw_rx, out_tx, Duration::from_millis(10)));
+
+    raw_tx.send(Ok(event("create", "/tmp/burst"))).await.unwrap();
+    raw_tx.send(Ok(event("modify", "/tmp/burst"))).await.unwrap();
+    // Closing the watcher side flushes all pending entries.
+    drop(raw_tx);
+
+    let flushed = out_rx.recv().await.unwrap().unwrap();
+    assert_eq!(flushed.kind, "create");
+    assert_eq!(flushed.paths, vec![PathBuf::from("/tmp/burst")]);
+    assert!(out_rx.recv().await.is_none());
+  }
+
+  #[tokio::test]
+  async fn debounce_keeps_move_paired() {
+    let (mut raw_tx, raw_rx) = mpsc::channel(16);
+    let (out_tx, mut out_rx) = mpsc::channel(16);
+    tokio::spawn(debounce_task(raw_rx, out_tx, Duration::from_millis(10)));
+
+    let from = PathBuf::from("/tmp/move_from");
+    let to = PathBuf::from("/tmp/move_to");
+    let mut rename = event("modify", "/tmp/move_from");
+    rename.detail = Some("rename:both".to_string());
+    rename.paths = vec![from.clone(), to.clone()];
+    raw_tx.send(Ok(rename)).await.unwrap();
+    drop(raw_tx);
+
+    let flushed = out_rx.recv().await.unwrap().unwrap();
+    assert_eq!(flushed.detail.as_deref(), Some("rename:both"));
+    assert_eq!(flushed.paths, vec![from, to]);
+    assert!(out_rx.recv().await.is_none());
+  }
+}